@@ -1,13 +1,19 @@
+use anyhow::bail;
 use anyhow::Context;
+use atty::Stream;
 use clap::crate_version;
-use clap::{App, Arg};
+use clap::{App, Arg, Shell};
 use fastmod::*;
 use grep::regex::RegexMatcherBuilder;
+use ignore::types::TypesBuilder;
 use regex::RegexBuilder;
 use rprompt::prompt_reply_stderr;
 
-fn fastmod() -> Result<()> {
-    let matches = App::new("fastmod")
+/// Builds the clap `App` describing fastmod's full argument set. Split out
+/// from `fastmod()` so it can also be handed to clap's completion
+/// generator for `--generate-completions`.
+fn build_app() -> App<'static, 'static> {
+    App::new("fastmod")
         .about("fastmod is a fast partial replacement for codemod.")
         .version(crate_version!())
         .long_about(
@@ -97,6 +103,29 @@ compatibility with the original codemod.",
                 .long("hidden")
                 .help("Search hidden files.")
         )
+        .arg(
+            Arg::with_name("type")
+                .short("t")
+                .long("type")
+                .value_name("TYPE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Only process files matching TYPE (e.g. 'rust', 'py'). May be repeated."),
+        )
+        .arg(
+            Arg::with_name("type_not")
+                .short("T")
+                .long("type-not")
+                .value_name("TYPE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Do not process files matching TYPE. May be repeated."),
+        )
+        .arg(
+            Arg::with_name("type_list")
+                .long("type-list")
+                .help("Show all supported file types and their associated globs, then exit."),
+        )
         .arg(
             Arg::with_name("iglob")
             .long("iglob")
@@ -114,28 +143,110 @@ compatibility with the original codemod.",
                 .long("print-changed-files")
                 .help("Print the paths of changed files. (Recommended to be combined with --accept-all.)"),
         )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .value_name("WHEN")
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto")
+                .help(
+                    "Control when to color the interactive diff and prompt: auto (default, \
+                     based on whether stderr is a terminal), always, or never.",
+                ),
+        )
+        .arg(
+            Arg::with_name("exec")
+                .short("x")
+                .long("exec")
+                .value_name("CMD")
+                .help(
+                    "Run CMD once per changed file, after it is saved (most useful with \
+                     --accept-all). Supports fd-style placeholders: {} full path, {/} basename, \
+                     {//} parent dir, {.} path without extension, {/.} basename without \
+                     extension. A CMD with no placeholder gets the path appended.",
+                )
+                .conflicts_with("exec_batch"),
+        )
+        .arg(
+            Arg::with_name("exec_batch")
+                .long("exec-batch")
+                .value_name("CMD")
+                .help("Like --exec, but run CMD once with every changed path, instead of once per file. The paths replace a {} argument, or are appended if CMD has no {}.")
+                .conflicts_with("exec"),
+        )
         .arg(
             Arg::with_name("fixed_strings")
                 .long("fixed-strings")
                 .short("F")
                 .help("Treat REGEX as a literal string. Avoids the need to escape regex metacharacters (compare to ripgrep's option of the same name).")
         )
+        .arg(
+            Arg::with_name("expr")
+                .long("expr")
+                .value_name("REGEX=SUBST")
+                .multiple(true)
+                .number_of_values(1)
+                .help("A regex=subst rule to apply. A literal = in REGEX must be escaped as \\=. May be repeated to apply several rules in a single pass over the tree (mirrors ripgrep's -e). Conflicts with the positional REGEX/SUBST form.")
+                .conflicts_with_all(&["match", "subst"]),
+        )
+        .arg(
+            Arg::with_name("rules")
+                .long("rules")
+                .value_name("FILE")
+                .help(
+                    "Read an ordered list of regex/subst rules from FILE and apply them all in \
+                     one traversal (ripgrep's -f, for codemods). Lines are tab-separated \
+                     REGEX<TAB>SUBST, with an optional third FLAGS column made of 'i' \
+                     (ignore-case), 'm' (multiline) and/or 'F' (fixed-strings) to override the \
+                     command-line flags for that rule alone; blank lines and lines starting with \
+                     # are ignored. Combines with --expr and conflicts with the positional \
+                     REGEX/SUBST form.",
+                )
+                .conflicts_with_all(&["match", "subst"]),
+        )
         .arg(
             Arg::with_name("match")
                 .value_name("REGEX")
                 .help("Regular expression to match.")
-                .required(true)
+                .required_unless_one(&["expr", "rules", "generate_completions", "type_list"])
                 .index(1),
         )
         .arg(
             Arg::with_name("subst")
              // TODO: support empty substitution to mean "open my
              // editor at instances of this regex"?
-             .required(true)
+             .required_unless_one(&["expr", "rules", "generate_completions", "type_list"])
              .help("Substitution to replace with.")
              .index(2),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("generate_completions")
+                .long("generate-completions")
+                .value_name("SHELL")
+                .possible_values(&["bash", "zsh", "fish", "powershell"])
+                .help("Generate a shell completion script on stdout and exit."),
+        )
+}
+
+fn fastmod() -> Result<()> {
+    let matches = build_app().get_matches();
+    if let Some(shell) = matches.value_of("generate_completions") {
+        let shell = shell
+            .parse::<Shell>()
+            .map_err(|e| anyhow::anyhow!("Unknown shell {:?}: {}", shell, e))?;
+        build_app().gen_completions_to("fastmod", shell, &mut std::io::stdout());
+        return Ok(());
+    }
+    if matches.is_present("type_list") {
+        let types = TypesBuilder::new()
+            .add_defaults()
+            .build()
+            .context("Unable to assemble the default file type definitions")?;
+        for def in types.definitions() {
+            println!("{}: {}", def.name(), def.globs().join(", "));
+        }
+        return Ok(());
+    }
     let multiline = matches.is_present("multiline");
     let dirs = {
         let mut dirs: Vec<_> = matches
@@ -149,13 +260,298 @@ compatibility with the original codemod.",
         dirs
     };
     let ignore_case = matches.is_present("ignore_case");
+    let fixed_strings = matches.is_present("fixed_strings");
     let file_set = get_file_set(&matches);
+    let types = build_types(&matches)?;
     let accept_all = matches.is_present("accept_all");
     let hidden = matches.is_present("hidden");
     let print_changed_files = matches.is_present("print_changed_files");
-    let regex_str = matches.value_of("match").expect("match is required!");
-    let subst = matches.value_of("subst").expect("subst is required!");
-    let (maybe_escaped_regex, subst) = if matches.is_present("fixed_strings") {
+    let color = match matches.value_of("color").unwrap_or("auto") {
+        "always" => true,
+        "never" => false,
+        // The diff and the accept/reject/edit prompt are both written to
+        // stderr (see `prompt_reply_stderr`), so that's the stream whose
+        // TTY-ness actually matters here, not stdout's.
+        _ => atty::is(Stream::Stderr),
+    };
+    let rules_file = matches
+        .value_of("rules")
+        .map(load_rules_file)
+        .transpose()?
+        .unwrap_or_default();
+    let raw_rules: Vec<(String, String, RuleFlags)> = if matches.is_present("expr")
+        || matches.is_present("rules")
+    {
+        matches
+            .values_of("expr")
+            .unwrap_or_default()
+            .map(|expr| parse_expr(expr).map(|(regex_str, subst)| (regex_str, subst, RuleFlags::default())))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .chain(rules_file)
+            .collect()
+    } else {
+        vec![(
+            matches.value_of("match").expect("match is required!").to_string(),
+            matches.value_of("subst").expect("subst is required!").to_string(),
+            RuleFlags::default(),
+        )]
+    };
+    let rules = raw_rules
+        .into_iter()
+        .map(|(regex_str, subst, flags)| {
+            build_rule(
+                &regex_str,
+                &subst,
+                flags.ignore_case.unwrap_or(ignore_case),
+                flags.multiline.unwrap_or(multiline),
+                flags.fixed_strings.unwrap_or(fixed_strings),
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let exec = if let Some(cmd) = matches.value_of("exec") {
+        Some(ExecSpec::new(cmd, false)?)
+    } else {
+        matches
+            .value_of("exec_batch")
+            .map(|cmd| ExecSpec::new(cmd, true))
+            .transpose()?
+    };
+
+    if accept_all {
+        Fastmod::run_fast(&rules, dirs, file_set, hidden, print_changed_files, exec, types)
+    } else {
+        Fastmod::new(accept_all, hidden, print_changed_files, color)
+            .run_interactive(&rules, dirs, file_set, exec, types)
+    }
+}
+
+/// Builds the `ignore::types::Types` matcher selected by `--type`/
+/// `--type-not`, to be fed into the walker alongside the extension/glob
+/// `file_set`. Returns `None` when neither flag was given, so callers can
+/// tell "no type filtering" apart from "filtered down to nothing".
+fn build_types(matches: &clap::ArgMatches) -> Result<Option<ignore::types::Types>> {
+    let selections = matches.values_of("type");
+    let negations = matches.values_of("type_not");
+    if selections.is_none() && negations.is_none() {
+        return Ok(None);
+    }
+    let mut builder = TypesBuilder::new();
+    builder.add_defaults();
+    for name in selections.into_iter().flatten() {
+        builder.select(name);
+    }
+    for name in negations.into_iter().flatten() {
+        builder.negate(name);
+    }
+    Ok(Some(builder.build().context(
+        "Unable to build a file type matcher from --type/--type-not (unrecognized type name?)",
+    )?))
+}
+
+/// A command to run against each file `fastmod` changes, as configured via
+/// `--exec`/`--exec-batch`. `batch` selects whether the command is invoked
+/// once per changed file or once with every changed path.
+pub struct ExecSpec {
+    template: CommandTemplate,
+    pub batch: bool,
+}
+
+impl ExecSpec {
+    fn new(cmd: &str, batch: bool) -> Result<Self> {
+        Ok(ExecSpec {
+            template: CommandTemplate::parse(cmd, batch)?,
+            batch,
+        })
+    }
+
+    /// Expands the template for a single changed file.
+    pub fn command_for(&self, path: &std::path::Path) -> std::process::Command {
+        self.template.build(&[path.to_path_buf()])
+    }
+
+    /// Expands the template for every changed file at once (`--exec-batch`).
+    pub fn command_for_batch(&self, paths: &[std::path::PathBuf]) -> std::process::Command {
+        self.template.build(paths)
+    }
+}
+
+/// A tokenized `--exec`/`--exec-batch` command, with fd-style placeholders:
+/// `{}` full path, `{/}` basename, `{//}` parent dir, `{.}` path without
+/// extension, `{/.}` basename without extension. A template with no
+/// placeholder gets the path(s) appended.
+struct CommandTemplate {
+    args: Vec<String>,
+    has_placeholder: bool,
+}
+
+const PLACEHOLDERS: &[&str] = &["{}", "{/}", "{//}", "{.}", "{/.}"];
+// Per-file placeholders (everything but the bare path). These can't be
+// aligned across files in a single --exec-batch command, so they're
+// rejected there the way fd rejects them for its -X/--exec-batch.
+const PER_FILE_PLACEHOLDERS: &[&str] = &["{/}", "{//}", "{.}", "{/.}"];
+
+impl CommandTemplate {
+    fn parse(cmd: &str, batch: bool) -> Result<Self> {
+        let args =
+            shell_words::split(cmd).with_context(|| format!("Unable to parse exec command {:?}", cmd))?;
+        if batch {
+            if let Some(arg) = args
+                .iter()
+                .find(|arg| PER_FILE_PLACEHOLDERS.iter().any(|p| arg.contains(p)))
+            {
+                bail!(
+                    "--exec-batch only supports the {{}} placeholder (got {:?}); per-file \
+                     placeholders like {{/}}, {{//}}, {{.}}, {{/.}} can't be aligned across \
+                     multiple files in a single batch command",
+                    arg
+                );
+            }
+        }
+        let has_placeholder = args
+            .iter()
+            .any(|arg| PLACEHOLDERS.iter().any(|p| arg.contains(p)));
+        Ok(CommandTemplate {
+            args,
+            has_placeholder,
+        })
+    }
+
+    /// Builds the command for one or more changed paths. An argument
+    /// containing a placeholder is expanded once per path (so `--exec-batch`
+    /// fans a `{}` argument out across every changed file); other
+    /// arguments are passed through once. With no placeholder at all, every
+    /// path is simply appended, as fd does.
+    fn build(&self, paths: &[std::path::PathBuf]) -> std::process::Command {
+        let mut full_args = Vec::new();
+        for arg in &self.args {
+            if self.has_placeholder && PLACEHOLDERS.iter().any(|p| arg.contains(p)) {
+                full_args.extend(paths.iter().map(|path| Self::expand(arg, path)));
+            } else {
+                full_args.push(arg.clone());
+            }
+        }
+        if !self.has_placeholder {
+            full_args.extend(paths.iter().map(|p| p.display().to_string()));
+        }
+        let mut command = std::process::Command::new(&full_args[0]);
+        command.args(&full_args[1..]);
+        command
+    }
+
+    fn expand(arg: &str, path: &std::path::Path) -> String {
+        let full = path.display().to_string();
+        let basename = path
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| full.clone());
+        let parent = path
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| basename.clone());
+        let without_ext = path.with_extension("").display().to_string();
+        arg.replace("{/.}", &stem)
+            .replace("{//}", &parent)
+            .replace("{/}", &basename)
+            .replace("{.}", &without_ext)
+            .replace("{}", &full)
+    }
+}
+
+/// Splits a `--expr` rule of the form `REGEX=SUBST` on the first unescaped
+/// `=`, so a REGEX that itself needs a literal `=` can write it as `\=`.
+fn parse_expr(expr: &str) -> Result<(String, String)> {
+    let mut regex_str = String::new();
+    let mut chars = expr.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && expr[i..].starts_with("\\=") {
+            regex_str.push('=');
+            chars.next(); // consume the escaped '='
+        } else if c == '=' {
+            return Ok((regex_str, expr[i + 1..].to_string()));
+        } else {
+            regex_str.push(c);
+        }
+    }
+    bail!(
+        "Expected --expr rule in REGEX=SUBST form (escape a literal = in REGEX as \\=), got {:?}",
+        expr
+    )
+}
+
+/// Per-rule overrides of the global `-i`/`-m`/`-F` flags, as set by the
+/// optional third column of a `--rules` file entry. `None` means "use
+/// whatever the command line set for this run".
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RuleFlags {
+    ignore_case: Option<bool>,
+    multiline: Option<bool>,
+    fixed_strings: Option<bool>,
+}
+
+/// Parses a rule's optional third column: a string of flag letters, `i`
+/// (ignore-case), `m` (multiline) and `F` (fixed-strings), each of which
+/// overrides the matching global flag for this rule only.
+fn parse_rule_flags(flags: &str) -> Result<RuleFlags> {
+    let mut parsed = RuleFlags::default();
+    for c in flags.chars() {
+        match c {
+            'i' => parsed.ignore_case = Some(true),
+            'm' => parsed.multiline = Some(true),
+            'F' => parsed.fixed_strings = Some(true),
+            _ => bail!("Unknown rule flag {:?} (expected some subset of \"imF\")", c),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Parses a `--rules` file into an ordered list of `(regex, subst, flags)`
+/// rules. Each non-blank, non-comment line is tab-separated
+/// `REGEX<TAB>SUBST` with an optional third `FLAGS` column (see
+/// `parse_rule_flags`); lines starting with `#` are comments.
+fn load_rules_file(path: &str) -> Result<Vec<(String, String, RuleFlags)>> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("Unable to read rules file {:?}", path))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let mut fields = line.split('\t');
+            let regex_str = fields.next();
+            let subst = fields.next();
+            match (regex_str, subst) {
+                (Some(regex_str), Some(subst)) => {
+                    let flags = fields.next().map(parse_rule_flags).transpose()?.unwrap_or_default();
+                    Ok((regex_str.to_string(), subst.to_string(), flags))
+                }
+                _ => bail!(
+                    "{}:{}: expected a tab-separated REGEX<TAB>SUBST rule, got {:?}",
+                    path,
+                    i + 1,
+                    line
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Builds a single `(Regex, RegexMatcher, subst)` rule. `ignore_case`,
+/// `multiline` and `fixed_strings` are the already-resolved flags for this
+/// rule: either the global CLI flags, or a `--rules` file entry's per-rule
+/// overrides.
+fn build_rule(
+    regex_str: &str,
+    subst: &str,
+    ignore_case: bool,
+    multiline: bool,
+    fixed_strings: bool,
+) -> Result<(regex::Regex, grep::regex::RegexMatcher, String)> {
+    let (maybe_escaped_regex, subst) = if fixed_strings {
         (regex::escape(regex_str), subst.replace("$", "$$"))
     } else {
         (regex_str.to_string(), subst.to_string())
@@ -178,21 +574,7 @@ not what you want. Press Enter to continue anyway or Ctrl-C to quit.",
         .multi_line(true)
         .dot_matches_new_line(multiline)
         .build(&maybe_escaped_regex)?;
-
-    if accept_all {
-        Fastmod::run_fast(
-            &regex,
-            &matcher,
-            &subst,
-            dirs,
-            file_set,
-            hidden,
-            print_changed_files,
-        )
-    } else {
-        Fastmod::new(accept_all, hidden, print_changed_files)
-            .run_interactive(&regex, &matcher, &subst, dirs, file_set)
-    }
+    Ok((regex, matcher, subst))
 }
 
 fn main() {
@@ -200,3 +582,220 @@ fn main() {
         eprint!("{:?}", e);
     }
 }
+
+#[cfg(test)]
+mod build_types_tests {
+    use super::{build_app, build_types};
+
+    #[test]
+    fn no_type_flags_means_no_filtering() {
+        let matches = build_app().get_matches_from(["fastmod", "foo", "bar"]);
+        assert!(build_types(&matches).unwrap().is_none());
+    }
+
+    #[test]
+    fn type_rust_matches_rs_files_only() {
+        let matches = build_app().get_matches_from(["fastmod", "--type", "rust", "foo", "bar"]);
+        let types = build_types(&matches).unwrap().expect("--type should build a matcher");
+        assert!(types.matched("src/main.rs", false).is_whitelist());
+        assert!(!types.matched("src/main.py", false).is_whitelist());
+    }
+
+    #[test]
+    fn type_not_excludes_matching_files() {
+        let matches = build_app().get_matches_from(["fastmod", "--type-not", "py", "foo", "bar"]);
+        let types = build_types(&matches).unwrap().expect("--type-not should build a matcher");
+        assert!(types.matched("src/main.py", false).is_ignore());
+    }
+}
+
+#[cfg(test)]
+mod rules_file_tests {
+    use super::{load_rules_file, parse_rule_flags, RuleFlags};
+    use std::io::Write;
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let file = tempfile_with(
+            "# a comment\n\nfoo\tbar\n\t\nbaz\tquux\t\n",
+        );
+        let rules = load_rules_file(file.path_str()).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                ("foo".to_string(), "bar".to_string(), RuleFlags::default()),
+                ("baz".to_string(), "quux".to_string(), RuleFlags::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_tab_is_an_error() {
+        let file = tempfile_with("no-tab-here\n");
+        assert!(load_rules_file(file.path_str()).is_err());
+    }
+
+    #[test]
+    fn per_rule_flags_override_independently() {
+        let file = tempfile_with("foo\tbar\ti\nbaz\tquux\tmF\n");
+        let rules = load_rules_file(file.path_str()).unwrap();
+        assert_eq!(
+            rules[0].2,
+            RuleFlags {
+                ignore_case: Some(true),
+                multiline: None,
+                fixed_strings: None,
+            }
+        );
+        assert_eq!(
+            rules[1].2,
+            RuleFlags {
+                ignore_case: None,
+                multiline: Some(true),
+                fixed_strings: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_flag_letter_is_an_error() {
+        assert!(parse_rule_flags("x").is_err());
+    }
+
+    struct TempFile {
+        path: std::path::PathBuf,
+    }
+
+    impl TempFile {
+        fn path_str(&self) -> &str {
+            self.path.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn tempfile_with(contents: &str) -> TempFile {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "fastmod-rules-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        TempFile { path }
+    }
+}
+
+#[cfg(test)]
+mod parse_expr_tests {
+    use super::parse_expr;
+
+    #[test]
+    fn splits_on_first_unescaped_equals() {
+        assert_eq!(
+            parse_expr("foo=bar").unwrap(),
+            ("foo".to_string(), "bar".to_string())
+        );
+    }
+
+    #[test]
+    fn escaped_equals_stays_in_the_regex() {
+        assert_eq!(
+            parse_expr("a\\=b=c").unwrap(),
+            ("a=b".to_string(), "c".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_equals_is_an_error() {
+        assert!(parse_expr("no-equals-here").is_err());
+    }
+}
+
+#[cfg(test)]
+mod command_template_tests {
+    use super::{CommandTemplate, ExecSpec};
+    use std::path::PathBuf;
+
+    fn args_for(cmd: &str, batch: bool, paths: &[&str]) -> Vec<String> {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        CommandTemplate::parse(cmd, batch)
+            .unwrap()
+            .build(&paths)
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn respects_quoting() {
+        let args = args_for("git commit -m \"auto format\"", false, &["a.txt"]);
+        assert_eq!(args, vec!["commit", "-m", "auto format", "a.txt"]);
+    }
+
+    #[test]
+    fn bare_command_appends_every_path() {
+        let args = args_for("rustfmt", true, &["a.txt", "b.txt"]);
+        assert_eq!(args, vec!["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn full_path_placeholder() {
+        let args = args_for("cat {}", false, &["a/x.txt"]);
+        assert_eq!(args, vec!["a/x.txt"]);
+    }
+
+    #[test]
+    fn bare_placeholder_expands_across_every_path_in_batch_mode() {
+        let args = args_for("echo {}", true, &["a/x.txt", "b/y.txt"]);
+        assert_eq!(args, vec!["a/x.txt", "b/y.txt"]);
+    }
+
+    #[test]
+    fn per_file_placeholder_is_fine_outside_batch_mode() {
+        let args = args_for("echo {/}", false, &["a/x.txt"]);
+        assert_eq!(args, vec!["x.txt"]);
+    }
+
+    #[test]
+    fn batch_rejects_per_file_placeholder() {
+        assert!(CommandTemplate::parse("echo {/}", true).is_err());
+    }
+
+    #[test]
+    fn batch_rejects_mixed_placeholders() {
+        // Per-file placeholders can't be aligned across files in one batch
+        // command, even alongside the bare {} placeholder.
+        assert!(CommandTemplate::parse("diff {} {.}.orig", true).is_err());
+    }
+
+    #[test]
+    fn command_for_builds_a_single_file_command() {
+        let spec = ExecSpec::new("cat {}", false).unwrap();
+        let args: Vec<String> = spec
+            .command_for(std::path::Path::new("a/x.txt"))
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(args, vec!["a/x.txt"]);
+    }
+
+    #[test]
+    fn command_for_batch_builds_a_multi_file_command() {
+        let spec = ExecSpec::new("echo {}", true).unwrap();
+        let paths = vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+        let args: Vec<String> = spec
+            .command_for_batch(&paths)
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(args, vec!["a.txt", "b.txt"]);
+    }
+}